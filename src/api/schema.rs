@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ApplicationConfig {
+    #[serde(rename = "apiVersion")]
+    pub api_version: String,
+
+    #[serde(rename = "kind")]
+    pub kind: String,
+
+    #[serde(rename = "spec")]
+    pub spec: Spec,
+}
+
+/// a single host/port pair for an ollama or qdrant replica
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Endpoint {
+    #[serde(rename = "url")]
+    pub url: String,
+
+    #[serde(rename = "port")]
+    pub port: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Spec {
+    // one or more qdrant replicas; a transient failure on one rotates to
+    // the next rather than failing the request outright
+    #[serde(rename = "qdrantEndpoints")]
+    pub qdrant_endpoints: Vec<Endpoint>,
+
+    // one or more ollama replicas, same failover behaviour as above
+    #[serde(rename = "ollamaEndpoints")]
+    pub ollama_endpoints: Vec<Endpoint>,
+
+    // attempts across the endpoint list (embed, search, generate each
+    // retry independently) before giving up and returning KO
+    #[serde(rename = "maxRetries")]
+    pub max_retries: u32,
+
+    #[serde(rename = "model")]
+    pub model: String,
+
+    #[serde(rename = "category")]
+    pub category: String,
+
+    #[serde(rename = "scoreThreshold")]
+    pub score_threshold: f32,
+
+    // number of candidates pulled from qdrant before MMR reranking
+    #[serde(rename = "topK")]
+    pub top_k: u64,
+
+    // relevance/diversity tradeoff for MMR reranking; 1.0 is pure
+    // relevance, 0.0 is pure diversity
+    #[serde(rename = "lambda")]
+    pub lambda: f32,
+
+    // number of MMR-selected chunks concatenated into the generation
+    // context
+    #[serde(rename = "contextCount")]
+    pub context_count: usize,
+
+    // size of the background worker pool draining the /ingest job queue
+    #[serde(rename = "ingestWorkers")]
+    pub ingest_workers: usize,
+}
@@ -0,0 +1,134 @@
+use custom_logger::*;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Bytes;
+use hyper::{Method, Request, Response};
+use multer::Multipart;
+use serde::{Deserialize, Serialize};
+
+use crate::handler::payload::error_response;
+use crate::queue::JobQueue;
+
+// size guards mirroring the /query handler's body-size check: refuse a
+// single field or a whole upload that is clearly not a markdown/text doc
+const MAX_FIELD_SIZE: u64 = 1024 * 1024 * 8;
+const MAX_STREAM_SIZE: u64 = 1024 * 1024 * 32;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct IngestAccepted {
+    #[serde(rename = "status")]
+    pub status: String,
+
+    #[serde(rename = "jobId")]
+    pub job_id: String,
+
+    #[serde(rename = "category")]
+    pub category: String,
+
+    #[serde(rename = "source")]
+    pub source: String,
+}
+
+/// handler - accepts a multipart upload of a markdown/text file plus a
+/// `category` field, enqueues it for background chunking/embedding/
+/// upserting and returns a job id immediately instead of blocking the
+/// request on the whole document being indexed
+pub async fn process_ingest(
+    req: Request<hyper::body::Incoming>,
+    log: &Logging,
+    queue: &JobQueue,
+) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+    match (req.method(), req.uri().path()) {
+        (&Method::POST, "/ingest") => {
+            let content_type = req
+                .headers()
+                .get(hyper::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .unwrap_or("")
+                .to_string();
+
+            let boundary = match multer::parse_boundary(&content_type) {
+                Ok(b) => b,
+                Err(e) => {
+                    return Ok(error_response(
+                        hyper::StatusCode::BAD_REQUEST,
+                        &format!("expected multipart/form-data: {:#?}", e),
+                    ));
+                }
+            };
+
+            let max = req.body().size_hint().upper().unwrap_or(u64::MAX);
+            if max > MAX_STREAM_SIZE {
+                return Ok(error_response(
+                    hyper::StatusCode::PAYLOAD_TOO_LARGE,
+                    "upload too big",
+                ));
+            }
+
+            let body_stream = req.into_body().into_data_stream();
+            let mut multipart = Multipart::with_constraints(
+                body_stream,
+                boundary,
+                multer::Constraints::new()
+                    .allowed_fields(vec!["file", "category", "source"])
+                    .size_limit(
+                        multer::SizeLimit::new()
+                            .per_field(MAX_FIELD_SIZE)
+                            .whole_stream(MAX_STREAM_SIZE),
+                    ),
+            );
+
+            let mut category: Option<String> = None;
+            let mut source: Option<String> = None;
+            let mut contents: Option<String> = None;
+
+            while let Some(field) = multipart.next_field().await? {
+                match field.name().map(|s| s.to_string()).as_deref() {
+                    Some("category") => category = Some(field.text().await?),
+                    Some("source") => source = Some(field.text().await?),
+                    Some("file") => {
+                        let file_name = field.file_name().map(|s| s.to_string());
+                        let bytes = field.bytes().await?;
+                        contents = Some(String::from_utf8(bytes.to_vec())?);
+                        if source.is_none() {
+                            source = file_name;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            let (category, source, contents) = match (category, source, contents) {
+                (Some(c), Some(s), Some(t)) => (c, s, t),
+                _ => {
+                    return Ok(error_response(
+                        hyper::StatusCode::BAD_REQUEST,
+                        "multipart body must include a \"file\" and a \"category\" field",
+                    ));
+                }
+            };
+
+            log.info(&format!(
+                "queuing ingest of {} into category {}",
+                source, category
+            ));
+            let job_id = queue
+                .enqueue(category.clone(), source.clone(), contents)
+                .await;
+
+            let accepted = IngestAccepted {
+                status: "QUEUED".to_string(),
+                job_id,
+                category,
+                source,
+            };
+            let resp_json = serde_json::to_string(&accepted).unwrap();
+            let mut resp = Response::new(Full::new(Bytes::from(resp_json)));
+            *resp.status_mut() = hyper::StatusCode::ACCEPTED;
+            Ok(resp)
+        }
+        _ => Ok(error_response(
+            hyper::StatusCode::NOT_FOUND,
+            "ensure you post to the /ingest endpoint with a multipart file and category",
+        )),
+    }
+}
@@ -0,0 +1,33 @@
+use custom_logger::*;
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::{Method, Request, Response};
+
+use crate::handler::payload::error_response;
+use crate::queue::JobQueue;
+
+/// handler - reports progress (chunks done / total) or the terminal
+/// success/failure of a job previously enqueued by `POST /ingest`
+pub async fn process_jobs(
+    req: Request<hyper::body::Incoming>,
+    _log: &Logging,
+    queue: &JobQueue,
+) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+    let path = req.uri().path().to_string();
+    match (req.method(), path.strip_prefix("/jobs/")) {
+        (&Method::GET, Some(id)) if !id.is_empty() => match queue.status(id).await {
+            Some(state) => {
+                let resp_json = serde_json::to_string(&state).unwrap();
+                Ok(Response::new(Full::new(Bytes::from(resp_json))))
+            }
+            None => Ok(error_response(
+                hyper::StatusCode::NOT_FOUND,
+                &format!("no job found with id {}", id),
+            )),
+        },
+        _ => Ok(error_response(
+            hyper::StatusCode::NOT_FOUND,
+            "ensure you get /jobs/{id} for a previously queued ingest job",
+        )),
+    }
+}
@@ -0,0 +1,4 @@
+pub mod ingest;
+pub mod jobs;
+pub mod payload;
+pub mod stream;
@@ -2,14 +2,18 @@ use custom_logger::*;
 use http_body_util::{BodyExt, Full};
 use hyper::body::*;
 use hyper::{Method, Request, Response};
+use metrics::histogram;
+use ollama_rs::generation::completion::request::GenerationRequest;
 use ollama_rs::Ollama;
 use qdrant_client::Qdrant;
 use serde::{Deserialize, Serialize};
-use std::fs;
 use std::str;
+use std::time::Instant;
 
 use crate::api::schema::*;
 use crate::qdrant::client::*;
+use crate::qdrant::mmr::mmr_select;
+use crate::upstream::query_with_retry;
 
 // pub type Result<T> = core::result::Result<T, Error>;
 
@@ -37,6 +41,43 @@ pub struct ResponseDetails {
 
     #[serde(rename = "score")]
     pub score: String,
+
+    // id of the payload (document) the context was sourced from, so
+    // callers can cite where the generated answer came from
+    #[serde(rename = "source")]
+    pub source: Option<String>,
+
+    // the individual MMR-selected matches that made up the generation
+    // context, each with its own relevance score
+    #[serde(rename = "matches")]
+    pub matches: Vec<MatchDetail>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct MatchDetail {
+    #[serde(rename = "source")]
+    pub source: String,
+
+    #[serde(rename = "score")]
+    pub score: String,
+}
+
+/// build a KO `ResponseDetails` body with the given status code; shared
+/// by every handler (ingest, jobs, ...) that needs to report a plain
+/// error in the same envelope the /query route uses
+pub(crate) fn error_response(status: hyper::StatusCode, message: &str) -> Response<Full<Bytes>> {
+    let resp_details = ResponseDetails {
+        status: "KO".to_string(),
+        query: None,
+        score: 0.0.to_string(),
+        data: message.to_string(),
+        source: None,
+        matches: Vec::new(),
+    };
+    let resp_json = serde_json::to_string(&resp_details).unwrap();
+    let mut resp = Response::new(Full::new(Bytes::from(resp_json)));
+    *resp.status_mut() = status;
+    resp
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -59,77 +100,242 @@ impl PayloadInterface for ImplPayloadInterface {
         query: String,
     ) -> Result<ResponseDetails, Box<dyn std::error::Error>> {
         let result: ResponseDetails;
-        // use config to create both
-        // ollama client and qdrant client
-        // setup qdrant client
-        let client = Qdrant::from_url(&format!(
-            "{}:{}",
-            config.clone().spec.qdrant_url,
-            config.clone().spec.qdrant_port
-        ))
-        .build();
-
-        if client.is_err() {
-            let res_err = ResponseDetails {
-                status: "KO".to_string(),
-                query: None,
-                score: 0.0.to_string(),
-                data: format!("qdrant {:#?}", client.err().unwrap()),
-            };
-            return Ok(res_err);
-        }
+        let retrieved = self.retrieve(log, &config, &query).await?;
 
-        let qclient = VectorDB::new(client.unwrap());
-        let ollama = Ollama::new(config.spec.ollama_url, config.spec.ollama_port as u16);
-        log.debug(&format!("ollama connection {:#?}", ollama));
-
-        let res = ollama
-            .generate_embeddings(config.spec.model, query.clone(), None)
-            .await;
-        if res.is_err() {
-            let res_err = ResponseDetails {
-                status: "KO".to_string(),
-                query: None,
-                score: 0.0.to_string(),
-                data: format!("ollama {:#?}", res.err().unwrap()),
-            };
-            return Ok(res_err);
-        }
-
-        let vecdb_res = qclient.search(config.spec.category, res.unwrap()).await?;
-        if !vecdb_res.payload.is_empty() {
-            log.info(&format!("score {:#?}", vecdb_res.score));
-            if vecdb_res.score > config.spec.score_threshold {
-                let v = vecdb_res.payload["id"].as_str().unwrap().clone();
-                let markdown_data = fs::read_to_string(v)?;
+        match retrieved {
+            Retrieval::Err(data) => {
                 result = ResponseDetails {
-                    status: "OK".to_string(),
-                    query: Some(query.clone()),
-                    score: vecdb_res.score.clone().to_string(),
-                    data: markdown_data,
+                    status: "KO".to_string(),
+                    query: None,
+                    score: 0.0.to_string(),
+                    data,
+                    source: None,
+                    matches: Vec::new(),
                 };
-            } else {
+            }
+            Retrieval::NotFound => {
                 result = ResponseDetails {
                     status: "KO".to_string(),
                     query: Some(query.clone()),
                     score: 0.0.to_string(),
                     data: "I could not find any related info, please refine your prompt"
                         .to_string(),
+                    source: None,
+                    matches: Vec::new(),
+                };
+            }
+            Retrieval::Found { context, matches } => {
+                let generated = self.generate(log, &config, &context, &query).await;
+                let top = matches.first().cloned();
+                result = match generated {
+                    Ok(answer) => ResponseDetails {
+                        status: "OK".to_string(),
+                        query: Some(query.clone()),
+                        score: top.as_ref().map(|m| m.score.clone()).unwrap_or_default(),
+                        data: answer,
+                        source: top.map(|m| m.source),
+                        matches,
+                    },
+                    Err(e) => ResponseDetails {
+                        status: "KO".to_string(),
+                        query: Some(query.clone()),
+                        score: 0.0.to_string(),
+                        data: format!("generation failed: {:#?}", e),
+                        source: None,
+                        matches: Vec::new(),
+                    },
                 };
             }
-        } else {
-            result = ResponseDetails {
-                status: "KO".to_string(),
-                query: Some(query.clone()),
-                score: 0.0.to_string(),
-                data: "I could not find any related info, please refine your prompt".to_string(),
-            };
         }
         Ok(result)
     }
 }
 
-/// handler - reads json as input
+/// outcome of embedding the query, searching qdrant for the top-K
+/// candidates and reranking them with MMR
+pub(crate) enum Retrieval {
+    Found {
+        context: String,
+        matches: Vec<MatchDetail>,
+    },
+    NotFound,
+    Err(String),
+}
+
+impl ImplPayloadInterface {
+    /// embed the query, fetch the top-K candidates from the configured
+    /// category's collection, rerank them with MMR for diversity and
+    /// concatenate the selected chunks into a single context string;
+    /// shared by both the buffered and the streaming response paths
+    pub(crate) async fn retrieve(
+        &self,
+        log: &Logging,
+        config: &ApplicationConfig,
+        query: &str,
+    ) -> Result<Retrieval, Box<dyn std::error::Error>> {
+        let model = config.spec.model.clone();
+        let query_owned = query.to_string();
+        let embed_started = Instant::now();
+        let embed_res = query_with_retry(
+            log,
+            &config.spec.ollama_endpoints,
+            config.spec.max_retries,
+            move |ep| {
+                let model = model.clone();
+                let query_owned = query_owned.clone();
+                async move {
+                    let ollama = Ollama::new(ep.url, ep.port as u16);
+                    ollama
+                        .generate_embeddings(model, query_owned, None)
+                        .await
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+                }
+            },
+        )
+        .await;
+        histogram!("rag_embedding_duration_seconds").record(embed_started.elapsed().as_secs_f64());
+        let query_vector = match embed_res {
+            Ok(v) => v,
+            Err(e) => return Ok(Retrieval::Err(format!("ollama {:#?}", e))),
+        };
+
+        let category = config.spec.category.clone();
+        let top_k = config.spec.top_k;
+        let search_vector = query_vector.clone();
+        let search_started = Instant::now();
+        let search_res = query_with_retry(
+            log,
+            &config.spec.qdrant_endpoints,
+            config.spec.max_retries,
+            move |ep| {
+                let category = category.clone();
+                let vector = search_vector.clone();
+                async move {
+                    let client = Qdrant::from_url(&format!("{}:{}", ep.url, ep.port))
+                        .build()
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+                    VectorDB::new(client)
+                        .search_top_k(category, vector, top_k)
+                        .await
+                }
+            },
+        )
+        .await;
+        histogram!("rag_search_duration_seconds").record(search_started.elapsed().as_secs_f64());
+        let candidates = match search_res {
+            Ok(c) => c,
+            Err(e) => return Ok(Retrieval::Err(format!("qdrant {:#?}", e))),
+        };
+
+        if candidates.is_empty() {
+            return Ok(Retrieval::NotFound);
+        }
+
+        let best_score = candidates
+            .iter()
+            .map(|c| c.score)
+            .fold(f32::MIN, f32::max);
+        log.info(&format!("best score {:#?}", best_score));
+        if best_score <= config.spec.score_threshold {
+            return Ok(Retrieval::NotFound);
+        }
+
+        let selected = mmr_select(
+            &query_vector,
+            &candidates,
+            config.spec.lambda,
+            config.spec.context_count,
+        );
+
+        let mut context = String::new();
+        let mut matches = Vec::new();
+        for idx in selected {
+            let candidate = &candidates[idx];
+            let source_id = match candidate.payload.get("id").and_then(|v| v.as_str()) {
+                Some(id) => id.to_string(),
+                None => {
+                    log.error(&format!(
+                        "skipping candidate with missing/non-string id: {:#?}",
+                        candidate.payload
+                    ));
+                    continue;
+                }
+            };
+            // the chunk text is stored alongside the embedding at ingest
+            // time (see queue::run_job); `id`/`source` is whatever the
+            // caller named the upload, not a path on this server, so it
+            // must not be re-read from disk here
+            let chunk = match candidate.payload.get("text").and_then(|v| v.as_str()) {
+                Some(text) => text.to_string(),
+                None => {
+                    log.error(&format!(
+                        "skipping candidate {} with no stored text payload",
+                        source_id
+                    ));
+                    continue;
+                }
+            };
+            context.push_str(&chunk);
+            context.push_str("\n\n");
+            matches.push(MatchDetail {
+                source: source_id,
+                score: candidate.score.to_string(),
+            });
+        }
+
+        if matches.is_empty() {
+            return Ok(Retrieval::NotFound);
+        }
+
+        Ok(Retrieval::Found { context, matches })
+    }
+
+    /// build a prompt from the retrieved context plus the user query and
+    /// ask ollama's completion api to synthesize an answer
+    async fn generate(
+        &self,
+        log: &Logging,
+        config: &ApplicationConfig,
+        context: &str,
+        query: &str,
+    ) -> Result<String, Box<dyn std::error::Error>> {
+        let prompt = format!(
+            "Use the following context to answer the question. If the context does not contain \
+             the answer, say you don't know.\n\nContext:\n{}\n\nQuestion: {}",
+            context, query
+        );
+        let model = config.spec.model.clone();
+        let generation_started = Instant::now();
+
+        let result = query_with_retry(
+            log,
+            &config.spec.ollama_endpoints,
+            config.spec.max_retries,
+            move |ep| {
+                let model = model.clone();
+                let prompt = prompt.clone();
+                async move {
+                    let ollama = Ollama::new(ep.url, ep.port as u16);
+                    let req = GenerationRequest::new(model, prompt);
+                    ollama
+                        .generate(req)
+                        .await
+                        .map(|res| res.response)
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+                }
+            },
+        )
+        .await;
+        histogram!("rag_generation_duration_seconds")
+            .record(generation_started.elapsed().as_secs_f64());
+        result
+    }
+}
+
+/// handler - reads json as input; request-level latency/outcome metrics
+/// are recorded generically by the `main` dispatch loop for every route,
+/// /query included, so this only instruments the embedding/search/
+/// generation phases that are specific to the RAG pipeline
 pub async fn process_payload<T: PayloadInterface>(
     req: Request<hyper::body::Incoming>,
     log: &Logging,
@@ -145,6 +351,8 @@ pub async fn process_payload<T: PayloadInterface>(
                     score: 0.0.to_string(),
                     query: None,
                     data: "body too big".to_string(),
+                    source: None,
+                    matches: Vec::new(),
                 };
                 let resp_json = serde_json::to_string(&resp_details).unwrap();
                 let mut resp = Response::new(Full::new(Bytes::from(resp_json)));
@@ -172,6 +380,8 @@ pub async fn process_payload<T: PayloadInterface>(
                 score: 0.0.to_string(),
                 query: None,
                 data: "service is up".to_string(),
+                source: None,
+                matches: Vec::new(),
             };
             let resp_json = serde_json::to_string(&resp_details).unwrap();
             let mut final_resp = Response::new(Full::new(Bytes::from(resp_json)));
@@ -185,6 +395,8 @@ pub async fn process_payload<T: PayloadInterface>(
                 score: 0.0.to_string(),
                 query: None,
                 data: "ensure you post to the /query endpoint with valid json".to_string(),
+                source: None,
+                matches: Vec::new(),
             };
             let resp_json = serde_json::to_string(&resp_details).unwrap();
             let mut final_resp = Response::new(Full::new(Bytes::from(resp_json)));
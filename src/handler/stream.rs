@@ -0,0 +1,206 @@
+use custom_logger::*;
+use futures::StreamExt;
+use http_body_util::combinators::BoxBody;
+use http_body_util::{BodyExt, StreamBody};
+use hyper::body::{Bytes, Frame};
+use hyper::{Method, Request, Response};
+use metrics::histogram;
+use ollama_rs::generation::completion::request::GenerationRequest;
+use ollama_rs::Ollama;
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::api::schema::*;
+use crate::handler::payload::{ImplPayloadInterface, MatchDetail, QueryDetails, Retrieval};
+use crate::upstream::query_with_retry;
+
+/// a single line of the newline-delimited / SSE stream sent back to the
+/// client: either an incremental token or the terminal frame carrying
+/// provenance for the answer that was just streamed
+#[derive(serde::Serialize, Debug)]
+struct StreamFrame {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    matches: Vec<MatchDetail>,
+
+    done: bool,
+}
+
+fn sse_frame(frame: &StreamFrame) -> Frame<Bytes> {
+    let json = serde_json::to_string(frame).unwrap();
+    Frame::data(Bytes::from(format!("data: {}\n\n", json)))
+}
+
+/// build a single-frame SSE response, setting the same `text/event-stream`
+/// content-type the success path sets so a compliant SSE client can parse
+/// early-exit/error bodies too
+fn sse_response(
+    frame: &StreamFrame,
+    status: hyper::StatusCode,
+) -> Response<BoxBody<Bytes, Infallible>> {
+    let body = http_body_util::Full::new(Bytes::from(format!(
+        "data: {}\n\n",
+        serde_json::to_string(frame).unwrap()
+    )))
+    .map_err(|never| match never {})
+    .boxed();
+    let mut resp = Response::new(body);
+    *resp.status_mut() = status;
+    resp.headers_mut().insert(
+        hyper::header::CONTENT_TYPE,
+        hyper::header::HeaderValue::from_static("text/event-stream"),
+    );
+    resp
+}
+
+/// handler - streams the generated answer back to the caller token by
+/// token over `/query/stream` instead of buffering the whole response
+pub async fn process_stream(
+    req: Request<hyper::body::Incoming>,
+    log: &Logging,
+    config: ApplicationConfig,
+    q: ImplPayloadInterface,
+) -> Result<Response<BoxBody<Bytes, Infallible>>, Box<dyn std::error::Error + Send + Sync>> {
+    match (req.method(), req.uri().path()) {
+        (&Method::POST, "/query/stream") => {
+            let req_body = req.collect().await?.to_bytes();
+            let payload = String::from_utf8(req_body.to_vec())?;
+            let query_json: QueryDetails = serde_json::from_str(&payload)?;
+            let query = query_json.query;
+
+            let retrieved = q
+                .retrieve(log, &config, &query)
+                .await
+                .map_err(|e| format!("{:#?}", e))?;
+            let (context, matches) = match retrieved {
+                Retrieval::Found { context, matches } => (context, matches),
+                Retrieval::NotFound => {
+                    let frame = StreamFrame {
+                        token: Some(
+                            "I could not find any related info, please refine your prompt"
+                                .to_string(),
+                        ),
+                        matches: Vec::new(),
+                        done: true,
+                    };
+                    return Ok(sse_response(&frame, hyper::StatusCode::OK));
+                }
+                Retrieval::Err(err) => {
+                    let frame = StreamFrame {
+                        token: Some(err),
+                        matches: Vec::new(),
+                        done: true,
+                    };
+                    return Ok(sse_response(&frame, hyper::StatusCode::INTERNAL_SERVER_ERROR));
+                }
+            };
+
+            let prompt = format!(
+                "Use the following context to answer the question. If the context does not \
+                 contain the answer, say you don't know.\n\nContext:\n{}\n\nQuestion: {}",
+                context, query
+            );
+            let model = config.spec.model.clone();
+            log.debug(&format!("streaming generation for query {:#?}", query));
+
+            let generation_started = Instant::now();
+            let token_stream = query_with_retry(
+                log,
+                &config.spec.ollama_endpoints,
+                config.spec.max_retries,
+                move |ep| {
+                    let model = model.clone();
+                    let prompt = prompt.clone();
+                    async move {
+                        let ollama = Ollama::new(ep.url, ep.port as u16);
+                        let gen_req = GenerationRequest::new(model, prompt);
+                        ollama
+                            .generate_stream(gen_req)
+                            .await
+                            .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+                    }
+                },
+            )
+            .await
+            .map_err(|e| format!("{:#?}", e))?;
+            // mirrors the buffered path's rag_generation_duration_seconds;
+            // only covers standing the stream up (the failover-eligible
+            // part), not the time spent draining tokens out of it
+            histogram!("rag_generation_duration_seconds")
+                .record(generation_started.elapsed().as_secs_f64());
+
+            // surface a mid-stream ollama error as a terminal error frame
+            // rather than silently treating it as an empty batch of tokens;
+            // the failover/retry pass already happened before the stream was
+            // established, so a later chunk error ends the response here.
+            // `errored` is only ever read after the token stream above it
+            // has been fully drained (`chain`'s second stream isn't polled
+            // until the first completes), so the trailing matches frame is
+            // suppressed once an error has already closed the stream with
+            // its own terminal frame.
+            let errored = Arc::new(AtomicBool::new(false));
+            let errored_for_chain = errored.clone();
+
+            let frames = token_stream
+                .flat_map(move |chunk| {
+                    let frames: Vec<Result<Frame<Bytes>, Infallible>> = match chunk {
+                        Ok(responses) => responses
+                            .into_iter()
+                            .map(|r| {
+                                Ok(sse_frame(&StreamFrame {
+                                    token: Some(r.response),
+                                    matches: Vec::new(),
+                                    done: false,
+                                }))
+                            })
+                            .collect(),
+                        Err(e) => {
+                            errored.store(true, Ordering::SeqCst);
+                            vec![Ok(sse_frame(&StreamFrame {
+                                token: Some(format!("generation failed mid-stream: {:#?}", e)),
+                                matches: Vec::new(),
+                                done: true,
+                            }))]
+                        }
+                    };
+                    futures::stream::iter(frames)
+                })
+                .map(Some)
+                .chain(futures::stream::once(async move {
+                    if errored_for_chain.load(Ordering::SeqCst) {
+                        None
+                    } else {
+                        Some(Ok::<_, Infallible>(sse_frame(&StreamFrame {
+                            token: None,
+                            matches,
+                            done: true,
+                        })))
+                    }
+                }))
+                .filter_map(futures::future::ready);
+
+            let body = StreamBody::new(frames).boxed();
+
+            let mut resp = Response::new(body);
+            resp.headers_mut().insert(
+                hyper::header::CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static("text/event-stream"),
+            );
+            Ok(resp)
+        }
+        _ => {
+            let frame = StreamFrame {
+                token: Some(
+                    "ensure you post to the /query/stream endpoint with valid json".to_string(),
+                ),
+                matches: Vec::new(),
+                done: true,
+            };
+            Ok(sse_response(&frame, hyper::StatusCode::NOT_FOUND))
+        }
+    }
+}
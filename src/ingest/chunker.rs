@@ -0,0 +1,184 @@
+/// a single window of text pulled out of a larger document, along with
+/// the byte offset into the source where it starts
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    pub text: String,
+    pub offset: usize,
+}
+
+// rough words-per-token estimate used to turn the configured token window
+// into a word count; good enough for chunk sizing, not exact tokenization
+const WORDS_PER_TOKEN: f32 = 0.75;
+
+/// split `text` into overlapping windows of approximately `window_tokens`
+/// tokens, carrying `overlap_tokens` tokens of the previous chunk's tail
+/// into the next one so a chunk boundary never fully severs context.
+/// chunks break only at sentence or paragraph boundaries (paragraph
+/// breaks are not preferred over sentence breaks), so a chunk never cuts
+/// a sentence in half; a single sentence that exceeds the window on its
+/// own becomes one oversized chunk.
+pub fn chunk_text(text: &str, window_tokens: usize, overlap_tokens: usize) -> Vec<Chunk> {
+    let window_words = ((window_tokens as f32) * WORDS_PER_TOKEN).round().max(1.0) as usize;
+    let overlap_words = ((overlap_tokens as f32) * WORDS_PER_TOKEN).round() as usize;
+
+    let units = split_into_units(text);
+    if units.is_empty() {
+        return Vec::new();
+    }
+
+    let mut chunks = Vec::new();
+    let mut start_idx = 0usize;
+    let mut idx = 0usize;
+    let mut words_in_group = 0usize;
+
+    while idx < units.len() {
+        let unit_words = word_count(&text[units[idx].0..units[idx].1]);
+        if idx > start_idx && words_in_group + unit_words > window_words {
+            chunks.push(make_chunk(text, &units, start_idx, idx));
+            start_idx = carry_start(text, &units, start_idx, idx, overlap_words);
+            words_in_group = units[start_idx..idx]
+                .iter()
+                .map(|(s, e)| word_count(&text[*s..*e]))
+                .sum();
+        }
+        words_in_group += unit_words;
+        idx += 1;
+    }
+    chunks.push(make_chunk(text, &units, start_idx, units.len()));
+
+    chunks
+}
+
+fn word_count(s: &str) -> usize {
+    s.split_whitespace().count().max(1)
+}
+
+fn make_chunk(text: &str, units: &[(usize, usize)], from: usize, to: usize) -> Chunk {
+    let start = units[from].0;
+    let end = units[to - 1].1;
+    Chunk {
+        text: text[start..end].to_string(),
+        offset: start,
+    }
+}
+
+/// find the latest unit index within `[start, end)` whose suffix holds
+/// at least `overlap_words` words (or `start` if none does), so that span
+/// becomes the start of the next chunk
+fn carry_start(
+    text: &str,
+    units: &[(usize, usize)],
+    start: usize,
+    end: usize,
+    overlap_words: usize,
+) -> usize {
+    if overlap_words == 0 {
+        return end;
+    }
+    let mut words = 0usize;
+    let mut i = end;
+    while i > start {
+        i -= 1;
+        let (s, e) = units[i];
+        words += word_count(&text[s..e]);
+        if words >= overlap_words {
+            break;
+        }
+    }
+    i
+}
+
+/// break text into paragraphs, then every paragraph into sentences,
+/// returning byte spans into the original `text`
+fn split_into_units(text: &str) -> Vec<(usize, usize)> {
+    let mut units = Vec::new();
+    let mut para_start = 0usize;
+    for (idx, _) in text.match_indices("\n\n") {
+        push_sentence_spans(text, para_start, idx, &mut units);
+        para_start = idx + 2;
+    }
+    push_sentence_spans(text, para_start, text.len(), &mut units);
+    units
+}
+
+fn push_sentence_spans(text: &str, from: usize, to: usize, units: &mut Vec<(usize, usize)>) {
+    let paragraph = &text[from..to];
+    let trimmed_start = from + (paragraph.len() - paragraph.trim_start().len());
+    let trimmed = paragraph.trim();
+    if trimmed.is_empty() {
+        return;
+    }
+
+    let mut start = trimmed_start;
+    for (i, b) in trimmed.as_bytes().iter().enumerate() {
+        if *b == b'.' || *b == b'?' || *b == b'!' {
+            let end = trimmed_start + i + 1;
+            if end > start {
+                units.push((start, end));
+            }
+            start = end;
+        }
+    }
+    if start < trimmed_start + trimmed.len() {
+        units.push((start, trimmed_start + trimmed.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_text_yields_no_chunks() {
+        assert_eq!(chunk_text("", 512, 64), Vec::new());
+        assert_eq!(chunk_text("   ", 512, 64), Vec::new());
+    }
+
+    #[test]
+    fn text_within_window_is_a_single_chunk() {
+        let chunks = chunk_text("One sentence. Another one.", 512, 64);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].offset, 0);
+        assert_eq!(chunks[0].text, "One sentence. Another one.");
+    }
+
+    #[test]
+    fn splits_on_window_and_carries_overlap_into_next_chunk() {
+        // 6 one-word sentences; a 2-word window with a 1-word overlap should
+        // split into multiple chunks, each sharing a trailing word with the
+        // chunk that follows it
+        let text = "Aaa. Bbb. Ccc. Ddd. Eee. Fff.";
+        let chunks = chunk_text(text, 2, 1);
+        assert!(chunks.len() > 1);
+        for pair in chunks.windows(2) {
+            let prev_tail = pair[0].text.split_whitespace().last().unwrap();
+            assert!(
+                pair[1].text.contains(prev_tail),
+                "expected {:#?} to carry over {} from {:#?}",
+                pair[1],
+                prev_tail,
+                pair[0]
+            );
+        }
+    }
+
+    #[test]
+    fn zero_overlap_does_not_carry_words_back() {
+        let text = "Aaa. Bbb. Ccc. Ddd.";
+        let chunks = chunk_text(text, 2, 0);
+        assert!(chunks.len() > 1);
+        // with no overlap, each chunk should start exactly where the
+        // previous one ended
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].offset + pair[0].text.len(), pair[1].offset);
+        }
+    }
+
+    #[test]
+    fn single_oversized_sentence_is_not_split() {
+        let text = "a b c d e f g h i j k l m n o p";
+        let chunks = chunk_text(text, 2, 1);
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].text, text);
+    }
+}
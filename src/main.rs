@@ -0,0 +1,103 @@
+mod api;
+mod handler;
+mod ingest;
+mod metrics;
+mod qdrant;
+mod queue;
+mod upstream;
+
+use custom_logger::*;
+use http_body_util::BodyExt;
+use hyper::body::Bytes;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper_util::rt::TokioIo;
+use std::convert::Infallible;
+use std::fs;
+use std::time::Instant;
+use tokio::net::TcpListener;
+
+use api::schema::ApplicationConfig;
+use handler::ingest::process_ingest;
+use handler::jobs::process_jobs;
+use handler::payload::{process_payload, ImplPayloadInterface};
+use handler::stream::process_stream;
+use queue::JobQueue;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let log = Logging::new();
+    log.info("starting rust-ragllm-qdrant");
+
+    let contents = fs::read_to_string("config.json")?;
+    let config: ApplicationConfig = serde_json::from_str(&contents)?;
+    let metrics_handle = metrics::install();
+    let job_queue = JobQueue::start(config.clone(), log.clone(), config.spec.ingest_workers);
+
+    let addr = "0.0.0.0:3000";
+    let listener = TcpListener::bind(addr).await?;
+    log.info(&format!("listening on {}", addr));
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let io = TokioIo::new(stream);
+        let log = log.clone();
+        let config = config.clone();
+        let metrics_handle = metrics_handle.clone();
+        let job_queue = job_queue.clone();
+
+        tokio::task::spawn(async move {
+            let service = service_fn(move |req| {
+                let log = log.clone();
+                let config = config.clone();
+                let metrics_handle = metrics_handle.clone();
+                let job_queue = job_queue.clone();
+                async move {
+                    // every route - not just /query - is timed and counted
+                    // here so streaming, ingest and jobs requests show up
+                    // in rag_request_duration_seconds/rag_requests_total
+                    // the same way the buffered /query path does
+                    let route = req.uri().path().to_string();
+                    let request_started = Instant::now();
+
+                    let response = if route == "/query/stream" {
+                        process_stream(req, &log, config, ImplPayloadInterface {})
+                            .await
+                            .map_err(|e| e as Box<dyn std::error::Error + Send + Sync>)
+                    } else if route == "/ingest" {
+                        process_ingest(req, &log, &job_queue)
+                            .await
+                            .map(|resp| resp.map(|b| b.map_err(|never: Infallible| match never {}).boxed()))
+                    } else if route.starts_with("/jobs/") {
+                        process_jobs(req, &log, &job_queue)
+                            .await
+                            .map(|resp| resp.map(|b| b.map_err(|never: Infallible| match never {}).boxed()))
+                    } else if route == "/metrics" {
+                        metrics::process_metrics(req, &metrics_handle)
+                            .await
+                            .map(|resp| resp.map(|b| b.map_err(|never: Infallible| match never {}).boxed()))
+                    } else {
+                        process_payload(req, &log, config, ImplPayloadInterface {})
+                            .await
+                            .map(|resp| resp.map(|b| b.map_err(|never: Infallible| match never {}).boxed()))
+                    };
+
+                    if let Ok(resp) = &response {
+                        let status = if resp.status().is_success() { "OK" } else { "KO" };
+                        metrics::record_request(
+                            &route,
+                            status,
+                            request_started.elapsed().as_secs_f64(),
+                        );
+                    }
+
+                    response
+                }
+            });
+
+            if let Err(err) = http1::Builder::new().serve_connection(io, service).await {
+                log.error(&format!("error serving connection: {:#?}", err));
+            }
+        });
+    }
+}
@@ -0,0 +1,59 @@
+use http_body_util::Full;
+use hyper::body::Bytes;
+use hyper::{Method, Request, Response};
+use metrics::{counter, histogram};
+use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
+
+/// install the global prometheus recorder; call once at startup before
+/// any `metrics::counter!`/`histogram!` call sites are exercised
+pub fn install() -> PrometheusHandle {
+    PrometheusBuilder::new()
+        .install_recorder()
+        .expect("failed to install prometheus recorder")
+}
+
+/// fold a request path down to a fixed label template before it ever
+/// reaches a metric: `/jobs/{id}` carries a fresh uuid per job, and
+/// labeling on the raw path would give prometheus one time series per
+/// job ever queued instead of one for the route
+fn route_label(route: &str) -> &str {
+    if route.starts_with("/jobs/") {
+        "/jobs/{id}"
+    } else {
+        route
+    }
+}
+
+/// record a completed request's latency and outcome; called once per
+/// request from the `main` dispatch loop so every route - not just
+/// /query - is counted and timed the same way
+pub fn record_request(route: &str, status: &str, elapsed_secs: f64) {
+    let route = route_label(route);
+    histogram!("rag_request_duration_seconds", "route" => route.to_string())
+        .record(elapsed_secs);
+    counter!("rag_requests_total", "route" => route.to_string(), "status" => status.to_string())
+        .increment(1);
+}
+
+/// handler - serves the text exposition format for scraping
+pub async fn process_metrics(
+    req: Request<hyper::body::Incoming>,
+    handle: &PrometheusHandle,
+) -> Result<Response<Full<Bytes>>, Box<dyn std::error::Error + Send + Sync>> {
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/metrics") => {
+            let body = handle.render();
+            let mut resp = Response::new(Full::new(Bytes::from(body)));
+            resp.headers_mut().insert(
+                hyper::header::CONTENT_TYPE,
+                hyper::header::HeaderValue::from_static("text/plain; version=0.0.4"),
+            );
+            Ok(resp)
+        }
+        _ => {
+            let mut resp = Response::new(Full::new(Bytes::from("not found")));
+            *resp.status_mut() = hyper::StatusCode::NOT_FOUND;
+            Ok(resp)
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use qdrant_client::qdrant::{
+    PointStruct, SearchPoints, UpsertPointsBuilder, WithPayloadSelector, WithVectorsSelector,
+};
+use qdrant_client::Qdrant;
+use serde_json::{Map, Value};
+
+/// thin wrapper around the qdrant_client so callers don't have to
+/// know about collection naming or payload shape
+#[derive(Clone, Debug)]
+pub struct VectorDB {
+    client: Qdrant,
+}
+
+/// a single candidate returned by `search_top_k`, carrying its own
+/// embedding vector so callers can rerank (e.g. via MMR) without a
+/// round trip back to qdrant
+#[derive(Clone, Debug)]
+pub struct Candidate {
+    pub score: f32,
+    pub payload: Map<String, Value>,
+    pub vector: Vec<f32>,
+}
+
+impl VectorDB {
+    pub fn new(client: Qdrant) -> Self {
+        VectorDB { client }
+    }
+
+    /// search the collection named after `category` for the `top_k`
+    /// candidates closest to the given embedding vector, each carrying
+    /// its own vector so the caller can rerank for diversity
+    pub async fn search_top_k(
+        &self,
+        category: String,
+        vector: Vec<f32>,
+        top_k: u64,
+    ) -> Result<Vec<Candidate>, Box<dyn std::error::Error>> {
+        let res = self
+            .client
+            .search_points(SearchPoints {
+                collection_name: category,
+                vector,
+                limit: top_k,
+                with_payload: Some(WithPayloadSelector::from(true)),
+                with_vectors: Some(WithVectorsSelector::from(true)),
+                ..Default::default()
+            })
+            .await?;
+
+        Ok(res
+            .result
+            .into_iter()
+            .map(|point| Candidate {
+                score: point.score,
+                payload: point
+                    .payload
+                    .into_iter()
+                    .map(|(k, v)| (k, v.into_json()))
+                    .collect(),
+                vector: point
+                    .vectors
+                    .and_then(|v| v.vectors_options)
+                    .and_then(|opts| match opts {
+                        qdrant_client::qdrant::vectors::VectorsOptions::Vector(v) => Some(v.data),
+                        _ => None,
+                    })
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    /// upsert a single embedding vector into the collection named after
+    /// `category`, storing `payload` (source path, chunk offset, ...)
+    /// alongside it so search results can be traced back to their origin
+    pub async fn upsert(
+        &self,
+        category: String,
+        id: u64,
+        vector: Vec<f32>,
+        payload: Map<String, Value>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let point = PointStruct::new(id, vector, payload);
+        self.client
+            .upsert_points(UpsertPointsBuilder::new(category, vec![point]))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use super::client::Candidate;
+
+/// rerank `candidates` with Maximal Marginal Relevance: starting from an
+/// empty selection, repeatedly pick the candidate that maximizes
+/// `lambda * sim(query, doc) - (1 - lambda) * max(sim(doc, selected))`
+/// until `n` are chosen (or candidates run out). `lambda` close to 1
+/// favors relevance, close to 0 favors diversity against what's already
+/// selected.
+pub fn mmr_select(query_vector: &[f32], candidates: &[Candidate], lambda: f32, n: usize) -> Vec<usize> {
+    let mut selected: Vec<usize> = Vec::new();
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+
+    while selected.len() < n && !remaining.is_empty() {
+        let (best_pos, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(pos, &idx)| {
+                let relevance = cosine_similarity(query_vector, &candidates[idx].vector);
+                let diversity_penalty = selected
+                    .iter()
+                    .map(|&s| cosine_similarity(&candidates[idx].vector, &candidates[s].vector))
+                    .fold(f32::MIN, f32::max);
+                let diversity_penalty = if selected.is_empty() {
+                    0.0
+                } else {
+                    diversity_penalty
+                };
+                let mmr_score = lambda * relevance - (1.0 - lambda) * diversity_penalty;
+                (pos, mmr_score)
+            })
+            .fold((0, f32::MIN), |best, cur| if cur.1 > best.1 { cur } else { best });
+
+        let idx = remaining.remove(best_pos);
+        selected.push(idx);
+    }
+
+    selected
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.is_empty() || b.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
+    let norm_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Map;
+
+    fn candidate(vector: Vec<f32>, score: f32) -> Candidate {
+        Candidate {
+            score,
+            payload: Map::new(),
+            vector,
+        }
+    }
+
+    #[test]
+    fn empty_candidates_selects_nothing() {
+        let selected = mmr_select(&[1.0, 0.0], &[], 0.5, 3);
+        assert_eq!(selected, Vec::<usize>::new());
+    }
+
+    #[test]
+    fn single_candidate_is_selected() {
+        let candidates = vec![candidate(vec![1.0, 0.0], 0.9)];
+        let selected = mmr_select(&[1.0, 0.0], &candidates, 0.5, 3);
+        assert_eq!(selected, vec![0]);
+    }
+
+    #[test]
+    fn lambda_one_is_pure_relevance_order() {
+        // two near-duplicate candidates and one less relevant one; with
+        // lambda = 1 the diversity penalty is ignored entirely, so the two
+        // duplicates should both be picked before the less relevant one
+        let candidates = vec![
+            candidate(vec![1.0, 0.0], 0.0),
+            candidate(vec![1.0, 0.0], 0.0),
+            candidate(vec![0.0, 1.0], 0.0),
+        ];
+        let selected = mmr_select(&[1.0, 0.0], &candidates, 1.0, 2);
+        assert_eq!(selected, vec![0, 1]);
+    }
+
+    #[test]
+    fn lambda_zero_prefers_diversity_against_selection() {
+        // once the first (most relevant) candidate is picked, lambda = 0
+        // should favor the most dissimilar remaining candidate next
+        let candidates = vec![
+            candidate(vec![1.0, 0.0], 0.0),
+            candidate(vec![1.0, 0.0], 0.0),
+            candidate(vec![0.0, 1.0], 0.0),
+        ];
+        let selected = mmr_select(&[1.0, 0.0], &candidates, 0.0, 2);
+        assert_eq!(selected, vec![0, 2]);
+    }
+
+    #[test]
+    fn n_larger_than_candidates_selects_all() {
+        let candidates = vec![candidate(vec![1.0, 0.0], 0.0), candidate(vec![0.0, 1.0], 0.0)];
+        let selected = mmr_select(&[1.0, 0.0], &candidates, 0.5, 10);
+        assert_eq!(selected.len(), 2);
+    }
+
+    #[test]
+    fn cosine_similarity_handles_empty_and_mismatched_vectors() {
+        assert_eq!(cosine_similarity(&[], &[1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 0.0]), 0.0);
+    }
+
+    #[test]
+    fn cosine_similarity_of_identical_vectors_is_one() {
+        let sim = cosine_similarity(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]);
+        assert!((sim - 1.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,271 @@
+use custom_logger::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::{mpsc, Mutex, RwLock};
+use uuid::Uuid;
+
+use crate::api::schema::ApplicationConfig;
+use crate::ingest::chunker::chunk_text;
+use crate::qdrant::client::VectorDB;
+use crate::upstream::query_with_retry;
+use ollama_rs::Ollama;
+use qdrant_client::Qdrant;
+use serde_json::{Map, Value};
+
+const CHUNK_WINDOW_TOKENS: usize = 512;
+const CHUNK_OVERLAP_TOKENS: usize = 64;
+
+/// lifecycle of a background ingestion job, mirroring pict-rs's
+/// queue/worker job states
+#[derive(Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// progress/terminal state for a single enqueued document, returned by
+/// `GET /jobs/{id}`
+#[derive(Serialize, Clone, Debug)]
+pub struct JobState {
+    #[serde(rename = "id")]
+    pub id: String,
+
+    #[serde(rename = "status")]
+    pub status: JobStatus,
+
+    #[serde(rename = "category")]
+    pub category: String,
+
+    #[serde(rename = "source")]
+    pub source: String,
+
+    #[serde(rename = "chunksDone")]
+    pub chunks_done: usize,
+
+    #[serde(rename = "chunksTotal")]
+    pub chunks_total: usize,
+
+    #[serde(rename = "chunksFailed")]
+    pub chunks_failed: usize,
+
+    #[serde(rename = "error")]
+    pub error: Option<String>,
+}
+
+/// a document waiting to be chunked, embedded and upserted by a worker
+struct IngestJob {
+    id: String,
+    category: String,
+    source: String,
+    contents: String,
+}
+
+/// in-process background ingestion queue: `POST /ingest` enqueues a job
+/// and returns immediately, a fixed pool of worker tasks drains the
+/// shared channel doing the actual chunk/embed/upsert work, and
+/// `GET /jobs/{id}` reads progress back out of the shared job table
+#[derive(Clone)]
+pub struct JobQueue {
+    sender: mpsc::Sender<IngestJob>,
+    jobs: Arc<RwLock<HashMap<String, JobState>>>,
+}
+
+impl JobQueue {
+    /// spawn `workers` worker tasks pulling from a shared channel; each
+    /// one runs the chunk/embed/upsert work for the jobs `process_ingest`
+    /// enqueues, after that request has already returned
+    pub fn start(config: ApplicationConfig, log: Logging, workers: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<IngestJob>(1024);
+        let jobs: Arc<RwLock<HashMap<String, JobState>>> = Arc::new(RwLock::new(HashMap::new()));
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for worker_id in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            let jobs = jobs.clone();
+            let config = config.clone();
+            let log = log.clone();
+            tokio::task::spawn(async move {
+                loop {
+                    let job = receiver.lock().await.recv().await;
+                    let Some(job) = job else {
+                        break;
+                    };
+                    log.debug(&format!("worker {} picked up job {}", worker_id, job.id));
+                    run_job(&log, &config, &jobs, job).await;
+                }
+            });
+        }
+
+        JobQueue { sender, jobs }
+    }
+
+    /// enqueue a document for background chunking/embedding/upsert and
+    /// return its freshly allocated job id
+    pub async fn enqueue(&self, category: String, source: String, contents: String) -> String {
+        let id = Uuid::new_v4().to_string();
+        let state = JobState {
+            id: id.clone(),
+            status: JobStatus::Queued,
+            category: category.clone(),
+            source: source.clone(),
+            chunks_done: 0,
+            chunks_total: 0,
+            chunks_failed: 0,
+            error: None,
+        };
+        self.jobs.write().await.insert(id.clone(), state);
+
+        // the channel is only ever closed once every worker task has
+        // ended, which only a panic can cause; the send error is then
+        // ignored and the job stays QUEUED
+        let _ = self
+            .sender
+            .send(IngestJob {
+                id: id.clone(),
+                category,
+                source,
+                contents,
+            })
+            .await;
+
+        id
+    }
+
+    /// look up a job's current progress or terminal state
+    pub async fn status(&self, id: &str) -> Option<JobState> {
+        self.jobs.read().await.get(id).cloned()
+    }
+}
+
+/// chunk, embed and upsert a single queued document, updating its shared
+/// job state as each chunk completes so `GET /jobs/{id}` can report
+/// progress while the work is still in flight
+async fn run_job(
+    log: &Logging,
+    config: &ApplicationConfig,
+    jobs: &Arc<RwLock<HashMap<String, JobState>>>,
+    job: IngestJob,
+) {
+    let chunks = chunk_text(&job.contents, CHUNK_WINDOW_TOKENS, CHUNK_OVERLAP_TOKENS);
+    log.info(&format!(
+        "job {}: indexing {} into category {} as {} chunks",
+        job.id,
+        job.source,
+        job.category,
+        chunks.len()
+    ));
+
+    if let Some(state) = jobs.write().await.get_mut(&job.id) {
+        state.status = JobStatus::Running;
+        state.chunks_total = chunks.len();
+    }
+
+    for (i, chunk) in chunks.iter().enumerate() {
+        let model = config.spec.model.clone();
+        let text = chunk.text.clone();
+        let embedding = query_with_retry(
+            log,
+            &config.spec.ollama_endpoints,
+            config.spec.max_retries,
+            move |ep| {
+                let model = model.clone();
+                let text = text.clone();
+                async move {
+                    let ollama = Ollama::new(ep.url, ep.port as u16);
+                    ollama
+                        .generate_embeddings(model, text, None)
+                        .await
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)
+                }
+            },
+        )
+        .await;
+        let embedding = match embedding {
+            Ok(e) => e,
+            Err(e) => {
+                log.error(&format!(
+                    "job {}: embedding chunk {} of {} failed: {:#?}",
+                    job.id, i, job.source, e
+                ));
+                if let Some(state) = jobs.write().await.get_mut(&job.id) {
+                    state.chunks_failed += 1;
+                }
+                continue;
+            }
+        };
+
+        let mut payload = Map::new();
+        payload.insert("id".to_string(), Value::String(job.source.clone()));
+        payload.insert("offset".to_string(), Value::from(chunk.offset));
+        payload.insert("text".to_string(), Value::String(chunk.text.clone()));
+
+        // deterministic point id so re-ingesting the same source at the
+        // same offset overwrites rather than duplicates
+        let point_id = point_id_for(&job.source, chunk.offset);
+        let category = job.category.clone();
+        let upsert_res = query_with_retry(
+            log,
+            &config.spec.qdrant_endpoints,
+            config.spec.max_retries,
+            move |ep| {
+                let category = category.clone();
+                let embedding = embedding.clone();
+                let payload = payload.clone();
+                async move {
+                    let client = Qdrant::from_url(&format!("{}:{}", ep.url, ep.port))
+                        .build()
+                        .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+                    VectorDB::new(client)
+                        .upsert(category, point_id, embedding, payload)
+                        .await
+                }
+            },
+        )
+        .await;
+        if let Err(e) = upsert_res {
+            log.error(&format!(
+                "job {}: upsert chunk {} of {} failed: {:#?}",
+                job.id, i, job.source, e
+            ));
+            if let Some(state) = jobs.write().await.get_mut(&job.id) {
+                state.chunks_failed += 1;
+            }
+            continue;
+        }
+
+        if let Some(state) = jobs.write().await.get_mut(&job.id) {
+            state.chunks_done += 1;
+        }
+    }
+
+    if let Some(state) = jobs.write().await.get_mut(&job.id) {
+        state.status = if state.chunks_total > 0 && state.chunks_failed == state.chunks_total {
+            state.error = Some("every chunk failed to index".to_string());
+            JobStatus::Failed
+        } else if state.chunks_failed > 0 {
+            // some but not all chunks indexed: still a terminal failure so
+            // `GET /jobs/{id}` doesn't read a partially-indexed document as
+            // a clean success
+            state.error = Some(format!(
+                "{} of {} chunks failed to index",
+                state.chunks_failed, state.chunks_total
+            ));
+            JobStatus::Failed
+        } else {
+            JobStatus::Completed
+        };
+    }
+}
+
+/// fold a source path and byte offset into a stable u64 point id
+fn point_id_for(source: &str, offset: usize) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source.hash(&mut hasher);
+    offset.hash(&mut hasher);
+    hasher.finish()
+}
@@ -0,0 +1,56 @@
+use custom_logger::*;
+use std::future::Future;
+use std::time::Duration;
+
+/// rotate through `endpoints` by attempt index so repeated failures cycle
+/// to the next configured replica rather than hammering the same one
+pub fn select_upstream<'a, T>(endpoints: &'a [T], attempt: u32) -> &'a T {
+    let idx = (attempt as usize) % endpoints.len();
+    &endpoints[idx]
+}
+
+/// run `attempt` against each of `endpoints` in turn, rotating on error
+/// for `max_retries` tries total (raised to the number of endpoints when
+/// lower, so every endpoint gets at least one try) with a short linear
+/// backoff between them, only giving up once every try has failed
+pub async fn query_with_retry<T, E, F, Fut>(
+    log: &Logging,
+    endpoints: &[E],
+    max_retries: u32,
+    mut attempt: F,
+) -> Result<T, Box<dyn std::error::Error>>
+where
+    E: Clone + std::fmt::Debug,
+    F: FnMut(E) -> Fut,
+    Fut: Future<Output = Result<T, Box<dyn std::error::Error>>>,
+{
+    if endpoints.is_empty() {
+        return Err("no upstream endpoints configured".into());
+    }
+
+    // never give up before every configured endpoint has had a turn, even
+    // if max_retries is set lower than the replica count
+    let attempts = max_retries.max(1).max(endpoints.len() as u32);
+
+    let mut last_err: Option<Box<dyn std::error::Error>> = None;
+    for i in 0..attempts {
+        let endpoint = select_upstream(endpoints, i).clone();
+        match attempt(endpoint.clone()).await {
+            Ok(v) => return Ok(v),
+            Err(e) => {
+                log.error(&format!(
+                    "upstream {:#?} failed (attempt {}/{}): {:#?}",
+                    endpoint,
+                    i + 1,
+                    attempts,
+                    e
+                ));
+                last_err = Some(e);
+                if i + 1 < attempts {
+                    tokio::time::sleep(Duration::from_millis(100 * (i as u64 + 1))).await;
+                }
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| "all upstreams exhausted".into()))
+}